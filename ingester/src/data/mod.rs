@@ -0,0 +1,36 @@
+//! Data buffered in memory by the ingester, organised per-shard and
+//! per-namespace.
+
+pub(crate) mod namespace;
+
+use snafu::Snafu;
+
+/// The outcome of attempting to apply a single [`dml::DmlOperation`] to
+/// buffered data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DmlApplyAction {
+    /// The operation was applied, buffering some data. The inner value
+    /// indicates if ingest should be paused due to memory pressure.
+    Applied(bool),
+    /// The operation was not applied: either every table write in it was
+    /// skipped, or it was a delete discarded under
+    /// [`namespace::DeletePolicy::Drop`] (under
+    /// [`namespace::DeletePolicy::Reject`] a delete fails with
+    /// [`Error::DeleteUnsupported`] instead).
+    Skipped,
+}
+
+/// Errors that can occur while buffering a [`dml::DmlOperation`] for a
+/// namespace.
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub(crate) enum Error {
+    #[snafu(display("delete operations are not supported by the ingester (table {table_name:?})"))]
+    DeleteUnsupported { table_name: Option<String> },
+
+    #[snafu(display(
+        "partition template for table {table_name} derives more than one partition key \
+         for a single write batch"
+    ))]
+    PartitionTemplateSpansMultipleBuckets { table_name: String },
+}
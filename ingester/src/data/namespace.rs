@@ -1,12 +1,21 @@
 //! Namespace level data buffer structures.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{btree_map::Entry, hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::Duration,
+};
 
-use data_types::{NamespaceId, SequenceNumber, ShardId, TableId};
+use chrono::{Datelike, NaiveDateTime};
+use data_types::{NamespaceId, PartitionKey, SequenceNumber, ShardId, TableId};
 use dml::DmlOperation;
-use metric::U64Counter;
+use metric::{Metric, U64Counter, U64Gauge};
+use mutable_batch::{column::ColumnData, MutableBatch};
 use observability_deps::tracing::warn;
 use parking_lot::RwLock;
+use schema::InfluxColumnType;
+use tokio::{sync::Notify, time::error::Elapsed};
 use write_summary::ShardProgress;
 
 #[cfg(test)]
@@ -45,6 +54,264 @@ impl DoubleRef {
     }
 }
 
+/// The maximum number of distinct series hashes retained per table before
+/// [`SeriesCardinalitySketch`] stops counting exactly and reports a
+/// lower-bound estimate instead.
+const SERIES_CARDINALITY_CAP: usize = 100_000;
+
+/// A bounded estimator of the number of distinct series (tag-value
+/// combinations) observed for a table.
+///
+/// Below [`SERIES_CARDINALITY_CAP`] this tracks series exactly; beyond it,
+/// new series stop being recorded so memory use stays fixed, and the
+/// reported count becomes a lower bound rather than an exact value.
+///
+/// TODO: swap the capped set for a HyperLogLog once one is vendored, so the
+/// estimate stays accurate past the cap instead of saturating.
+#[derive(Debug, Default)]
+struct SeriesCardinalitySketch {
+    seen: HashSet<u64>,
+}
+
+impl SeriesCardinalitySketch {
+    /// Record a series key hash.
+    fn observe(&mut self, series_hash: u64) {
+        if self.seen.len() < SERIES_CARDINALITY_CAP {
+            self.seen.insert(series_hash);
+        }
+    }
+
+    /// Returns the current cardinality estimate.
+    fn estimate(&self) -> u64 {
+        self.seen.len() as u64
+    }
+}
+
+/// Per-table ingest metrics recorders, lazily created the first time a table
+/// is observed.
+#[derive(Debug)]
+struct TableIngestMetrics {
+    rows_buffered: U64Counter,
+    series_cardinality: U64Gauge,
+    cardinality_sketch: SeriesCardinalitySketch,
+}
+
+/// Derive one series-key hash per row of `batch`, combining the values of
+/// all tag columns (i.e. the series key), for use with
+/// [`SeriesCardinalitySketch`].
+fn series_hashes(batch: &MutableBatch) -> Vec<u64> {
+    // Sort by column name so the hash is independent of the order
+    // `batch.columns()` happens to yield them in, which isn't guaranteed
+    // stable across `MutableBatch` instances for the same table (e.g. if
+    // tag columns were added to the schema at different times).
+    let mut tag_columns: Vec<_> = batch
+        .columns()
+        .filter(|(_, col)| col.influx_type() == InfluxColumnType::Tag)
+        .collect();
+    tag_columns.sort_unstable_by_key(|(name, _)| *name);
+
+    (0..batch.rows())
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            for (name, col) in &tag_columns {
+                name.hash(&mut hasher);
+                match col.data() {
+                    ColumnData::Tag(codes, dictionary, _) => {
+                        dictionary.lookup_id(codes[row]).hash(&mut hasher);
+                    }
+                    _ => unreachable!("tag column must contain tag column data"),
+                }
+            }
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Returns, for every row of `batch`, the value of the named tag column, or
+/// `None` if `batch` has no such column.
+fn tag_column_values(batch: &MutableBatch, tag_name: &str) -> Option<Vec<String>> {
+    let (_, col) = batch
+        .columns()
+        .find(|(name, col)| *name == tag_name && col.influx_type() == InfluxColumnType::Tag)?;
+
+    match col.data() {
+        ColumnData::Tag(codes, dictionary, _) => Some(
+            codes
+                .iter()
+                .map(|&code| {
+                    // A tag value that is actually absent from a row (a
+                    // null in the dictionary) must not collapse to the
+                    // same key component as a row where the tag is
+                    // present but set to the literal empty string; NUL
+                    // can't appear in a tag value parsed from line
+                    // protocol, so it's safe to use as a distinct marker
+                    // for "absent" here.
+                    dictionary
+                        .lookup_id(code)
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| "\0".to_string())
+                })
+                .collect(),
+        ),
+        _ => unreachable!("tag column must contain tag column data"),
+    }
+}
+
+/// Returns the timestamp, in nanoseconds since the epoch, of every row of
+/// `batch`, or `None` if `batch` has no timestamp column.
+fn row_timestamps(batch: &MutableBatch) -> Option<&[i64]> {
+    let (_, col) = batch
+        .columns()
+        .find(|(_, col)| col.influx_type() == InfluxColumnType::Timestamp)?;
+
+    match col.data() {
+        ColumnData::I64(data, _) => Some(data),
+        _ => unreachable!("timestamp column must contain i64 data"),
+    }
+}
+
+/// Formats `timestamp_nanos` as a partition-key time bucket at the given
+/// `granularity`.
+fn bucket_key(granularity: PartitionGranularity, timestamp_nanos: i64) -> String {
+    let secs = timestamp_nanos.div_euclid(1_000_000_000);
+    let nanos = timestamp_nanos.rem_euclid(1_000_000_000) as u32;
+    let dt = NaiveDateTime::from_timestamp_opt(secs, nanos).expect("timestamp out of range");
+
+    match granularity {
+        PartitionGranularity::Hourly => dt.format("%Y-%m-%d-%H").to_string(),
+        PartitionGranularity::Daily => dt.format("%Y-%m-%d").to_string(),
+        PartitionGranularity::Weekly => {
+            let week = dt.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+    }
+}
+
+/// Derive the effective partition key for `batch` according to `template`.
+///
+/// The time bucket is re-derived from `batch`'s own timestamps (rather than
+/// trusting `router_key`, which is always daily) so that a per-table
+/// granularity override actually changes the partition a row lands in, and
+/// any `template.tag_columns()` are folded in alongside it. Falls back to
+/// `router_key` unchanged if `batch` has no timestamp column, or no rows,
+/// to derive a bucket from.
+///
+/// `buffer_table_write()` takes a single partition key for the whole of
+/// `batch`, so every row of `batch` is required to derive the *same* key
+/// under `template` — e.g. a batch the router bucketed daily must not
+/// straddle an hour boundary once an hourly override is applied to it.
+/// Returns `Error::PartitionTemplateSpansMultipleBuckets` rather than
+/// silently filing some rows under the wrong key when that precondition
+/// doesn't hold; splitting `batch` per-bucket is left for when
+/// `buffer_table_write()` can accept more than one key per call.
+fn partition_key_for(
+    template: &PartitionTemplate,
+    router_key: &PartitionKey,
+    table_name: &TableName,
+    batch: &MutableBatch,
+) -> Result<PartitionKey, super::Error> {
+    let timestamps = match row_timestamps(batch) {
+        Some(ts) if !ts.is_empty() => ts,
+        _ => return Ok(router_key.clone()),
+    };
+
+    let tag_values: Vec<Vec<String>> = template
+        .tag_columns()
+        .iter()
+        .filter_map(|tag| tag_column_values(batch, tag))
+        .collect();
+
+    let mut keys = (0..timestamps.len()).map(|row| {
+        let mut key = bucket_key(template.granularity(), timestamps[row]);
+        for values in &tag_values {
+            key.push('-');
+            key.push_str(&values[row]);
+        }
+        key
+    });
+
+    let first = keys.next().expect("batch must have at least one row");
+    if keys.all(|k| k == first) {
+        Ok(PartitionKey::from(first))
+    } else {
+        Err(super::Error::PartitionTemplateSpansMultipleBuckets {
+            table_name: table_name.to_string(),
+        })
+    }
+}
+
+/// The time-bucket granularity used to derive a partition key for a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PartitionGranularity {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl Default for PartitionGranularity {
+    fn default() -> Self {
+        Self::Daily
+    }
+}
+
+/// A data-driven description of how a table's writes should be bucketed into
+/// partitions, in place of the previous compile-time constant of one daily
+/// partition per table.
+///
+/// A namespace has one default [`PartitionTemplate`], and may override it on
+/// a per-table basis (see [`NamespaceData::insert_table`]) so that
+/// high-ingest-rate tables can use a finer granularity than the rest of the
+/// namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PartitionTemplate {
+    granularity: PartitionGranularity,
+
+    /// Additional tag columns to fold into the partition key alongside the
+    /// time bucket, for tables that should also be split by tag value.
+    tag_columns: Vec<String>,
+}
+
+impl PartitionTemplate {
+    pub(crate) fn new(granularity: PartitionGranularity, tag_columns: Vec<String>) -> Self {
+        Self {
+            granularity,
+            tag_columns,
+        }
+    }
+
+    pub(crate) fn granularity(&self) -> PartitionGranularity {
+        self.granularity
+    }
+
+    pub(crate) fn tag_columns(&self) -> &[String] {
+        &self.tag_columns
+    }
+}
+
+/// The policy applied to [`DmlOperation::Delete`] operations in
+/// [`NamespaceData::buffer_operation`], since deletes are not currently
+/// applied to buffered data.
+///
+/// The default is [`Self::Reject`] so that an unsupported delete is never
+/// silently reported as having succeeded; callers that rely on the old
+/// warn-and-discard behaviour must opt into [`Self::Drop`] explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeletePolicy {
+    /// Fail the operation with `super::Error::DeleteUnsupported`.
+    Reject,
+    /// Log and discard the delete, reporting `DmlApplyAction::Skipped` so
+    /// progress accounting and back-pressure are not misled into believing
+    /// it was applied.
+    Drop,
+    // TODO: `Buffer` policy that actually applies deletes to buffered data.
+}
+
+impl Default for DeletePolicy {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
 /// The string name / identifier of a Namespace.
 ///
 /// A reference-counted, cheap clone-able string.
@@ -86,13 +353,31 @@ pub(crate) struct NamespaceData {
     tables: RwLock<DoubleRef>,
     table_count: U64Counter,
 
+    /// Per-table row-count and series-cardinality metric recorders, labelled
+    /// by namespace and table name, created on first use.
+    table_ingest_metrics: RwLock<HashMap<TableName, TableIngestMetrics>>,
+    rows_buffered_metric: Metric<U64Counter>,
+    series_cardinality_metric: Metric<U64Gauge>,
+
     /// The resolver of `(shard_id, table_id, partition_key)` to
     /// [`PartitionData`].
     ///
     /// [`PartitionData`]: super::partition::PartitionData
     partition_provider: Arc<dyn PartitionProvider>,
 
-    /// The sequence number being actively written, if any.
+    /// The partition template applied to tables that have no entry in
+    /// `table_partition_template_overrides`.
+    default_partition_template: PartitionTemplate,
+
+    /// Per-table partition template overrides, looked up on every write to
+    /// derive its partition key, and when a table is first inserted to create
+    /// its [`TableData`] (see [`Self::insert_table`]).
+    table_partition_template_overrides: RwLock<HashMap<TableName, PartitionTemplate>>,
+
+    /// The set of sequence numbers currently being written, each mapped to
+    /// the number of in-flight `buffer_operation()` calls for it (multiple
+    /// writes for the same sequence number can be in flight concurrently
+    /// across tables).
     ///
     /// This is used to know when a sequence number is only partially
     /// buffered for readability reporting. For example, in the
@@ -131,7 +416,17 @@ pub(crate) struct NamespaceData {
     ///                                                               PartitionData
     ///                                                       (Ingester state per partition)
     ///```
-    buffering_sequence_number: RwLock<Option<SequenceNumber>>,
+    buffering_sequence_numbers: RwLock<BTreeMap<SequenceNumber, usize>>,
+
+    /// Notified every time a buffer of a sequence number in
+    /// `buffering_sequence_numbers` completes (whether or not that removes
+    /// its entry), so that
+    /// [`Self::wait_readable()`] callers can re-check readability without
+    /// busy-polling [`Self::progress()`].
+    readable_notify: Notify,
+
+    /// The policy applied to unsupported delete operations.
+    delete_policy: DeletePolicy,
 
     /// Control the flow of ingest, for testing purposes
     #[cfg(test)]
@@ -139,12 +434,15 @@ pub(crate) struct NamespaceData {
 }
 
 impl NamespaceData {
-    /// Initialize new tables with default partition template of daily
+    /// Initialize new tables with `default_partition_template`, unless
+    /// overridden per-table (see [`Self::insert_table`]).
     pub(super) fn new(
         namespace_id: NamespaceId,
         namespace_name: NamespaceName,
         shard_id: ShardId,
         partition_provider: Arc<dyn PartitionProvider>,
+        default_partition_template: PartitionTemplate,
+        delete_policy: DeletePolicy,
         metrics: &metric::Registry,
     ) -> Self {
         let table_count = metrics
@@ -154,13 +452,29 @@ impl NamespaceData {
             )
             .recorder(&[]);
 
+        let rows_buffered_metric = metrics.register_metric(
+            "ingester_table_rows_buffered",
+            "Total number of rows buffered for a table, by namespace and table",
+        );
+        let series_cardinality_metric = metrics.register_metric(
+            "ingester_table_series_cardinality",
+            "Estimated number of distinct series buffered for a table, by namespace and table",
+        );
+
         Self {
             namespace_id,
             namespace_name,
             shard_id,
             tables: Default::default(),
             table_count,
-            buffering_sequence_number: RwLock::new(None),
+            table_ingest_metrics: Default::default(),
+            rows_buffered_metric,
+            series_cardinality_metric,
+            default_partition_template,
+            table_partition_template_overrides: Default::default(),
+            delete_policy,
+            buffering_sequence_numbers: RwLock::new(BTreeMap::new()),
+            readable_notify: Notify::new(),
             partition_provider,
             #[cfg(test)]
             test_triggers: TestTriggers::new(),
@@ -185,33 +499,62 @@ impl NamespaceData {
         // number. Since there is no namespace wide lock held during a
         // write, this number is used to detect and update reported
         // progress during a write
-        let _sequence_number_guard =
-            ScopedSequenceNumber::new(sequence_number, &self.buffering_sequence_number);
+        let _sequence_number_guard = ScopedSequenceNumber::new(
+            sequence_number,
+            &self.buffering_sequence_numbers,
+            &self.readable_notify,
+        );
 
         match dml_operation {
             DmlOperation::Write(write) => {
                 let mut pause_writes = false;
                 let mut all_skipped = true;
 
-                // Extract the partition key derived by the router.
-                let partition_key = write.partition_key().clone();
+                // Extract the partition key derived by the router, used as a
+                // fallback when a table's partition template has nothing to
+                // derive a bucket from.
+                let router_partition_key = write.partition_key().clone();
+
+                // Derive every table's effective partition key up front,
+                // before any table in this write is buffered, so a single
+                // table whose batch spans more than one partition bucket
+                // aborts the whole write rather than leaving it half
+                // applied (some tables buffered, others not) for a caller
+                // that retries the write wholesale.
+                let tables: Vec<(TableName, TableId, MutableBatch)> = write
+                    .into_tables()
+                    .map(|(table_name, table_id, b)| (TableName::from(table_name), table_id, b))
+                    .collect();
+                let partition_keys = tables
+                    .iter()
+                    .map(|(table_name, _, b)| {
+                        let template = self.partition_template_for(table_name);
+                        partition_key_for(&template, &router_partition_key, table_name, b)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                for (table_name, table_id, b) in write.into_tables() {
-                    let table_name = TableName::from(table_name);
+                for ((table_name, table_id, b), partition_key) in
+                    tables.into_iter().zip(partition_keys)
+                {
                     let table_data = match self.table_data(&table_name) {
                         Some(t) => t,
-                        None => self.insert_table(table_name, table_id).await?,
+                        None => self.insert_table(table_name.clone(), table_id).await?,
                     };
 
+                    // Derive the row/series stats before `b` is moved into
+                    // `buffer_table_write()` below, but defer updating the
+                    // metrics until it is known the write was actually
+                    // applied (as opposed to skipped or erroring out), so
+                    // rows that are merely attempted are never counted as
+                    // buffered.
+                    let rows = b.rows();
+                    let series_hashes = series_hashes(&b);
+
                     let action = table_data
-                        .buffer_table_write(
-                            sequence_number,
-                            b,
-                            partition_key.clone(),
-                            lifecycle_handle,
-                        )
+                        .buffer_table_write(sequence_number, b, partition_key, lifecycle_handle)
                         .await?;
                     if let DmlApplyAction::Applied(should_pause) = action {
+                        self.record_table_ingest_metrics(&table_name, rows, &series_hashes);
                         pause_writes = pause_writes || should_pause;
                         all_skipped = false;
                     }
@@ -227,19 +570,39 @@ impl NamespaceData {
                     Ok(DmlApplyAction::Applied(pause_writes))
                 }
             }
-            DmlOperation::Delete(delete) => {
+            DmlOperation::Delete(delete) => self.apply_delete_policy(
+                delete.table_name().map(ToString::to_string),
+                delete.meta().sequence().map(|s| s.sequence_number),
+            ),
+        }
+    }
+
+    /// Apply `self.delete_policy` to an unsupported delete for `table_name`
+    /// (with `sequence_number` included for logging), returning the outcome.
+    ///
+    /// Kept separate from `buffer_operation` (rather than matching on
+    /// `self.delete_policy` inline) so the policy decision itself is
+    /// unit-testable without needing to construct a real [`DmlOperation::Delete`].
+    fn apply_delete_policy(
+        &self,
+        table_name: Option<String>,
+        sequence_number: Option<SequenceNumber>,
+    ) -> Result<DmlApplyAction, super::Error> {
+        match self.delete_policy {
+            DeletePolicy::Reject => Err(super::Error::DeleteUnsupported { table_name }),
+            DeletePolicy::Drop => {
                 // Deprecated delete support:
                 // https://github.com/influxdata/influxdb_iox/issues/5825
                 warn!(
                     shard_id=%self.shard_id,
                     namespace_name=%self.namespace_name,
                     namespace_id=%self.namespace_id,
-                    table_name=?delete.table_name(),
-                    sequence_number=?delete.meta().sequence(),
+                    table_name=?table_name,
+                    sequence_number=?sequence_number,
                     "discarding unsupported delete op"
                 );
 
-                Ok(DmlApplyAction::Applied(false))
+                Ok(DmlApplyAction::Skipped)
             }
         }
     }
@@ -268,28 +631,148 @@ impl NamespaceData {
             None => {
                 self.table_count.inc(1);
 
+                let partition_template = self.partition_template_for(&table_name);
+
                 // Insert the table and then return a ref to it.
                 t.insert(TableData::new(
                     table_id,
                     table_name,
                     self.shard_id,
                     self.namespace_id,
+                    partition_template,
                     Arc::clone(&self.partition_provider),
                 ))
             }
         })
     }
 
+    /// Set a per-table [`PartitionTemplate`] override for `table_name`.
+    ///
+    /// The override is used to derive the partition key of every write to
+    /// `table_name` buffered after this call, including writes to a table
+    /// that is already buffering; the template that table's [`TableData`]
+    /// was created with is not updated.
+    pub(crate) fn set_table_partition_template(
+        &self,
+        table_name: TableName,
+        partition_template: PartitionTemplate,
+    ) {
+        self.table_partition_template_overrides
+            .write()
+            .insert(table_name, partition_template);
+    }
+
+    /// Resolve the [`PartitionTemplate`] to use for `table_name`: its
+    /// per-table override if one has been configured, otherwise the
+    /// namespace's default.
+    fn partition_template_for(&self, table_name: &TableName) -> PartitionTemplate {
+        self.table_partition_template_overrides
+            .read()
+            .get(table_name)
+            .cloned()
+            .unwrap_or_else(|| self.default_partition_template.clone())
+    }
+
+    /// Update the row-count and series-cardinality metrics for `table_name`
+    /// with `rows` newly-buffered rows whose series-key hashes are
+    /// `series_hashes`, creating the recorders on first use.
+    ///
+    /// Callers must only invoke this once a write is known to have actually
+    /// been applied, not merely attempted, so the metrics reflect rows
+    /// buffered rather than rows offered.
+    fn record_table_ingest_metrics(
+        &self,
+        table_name: &TableName,
+        rows: usize,
+        series_hashes: &[u64],
+    ) {
+        let mut table_metrics = self.table_ingest_metrics.write();
+        let entry = table_metrics
+            .entry(table_name.clone())
+            .or_insert_with(|| TableIngestMetrics {
+                rows_buffered: self.rows_buffered_metric.recorder(&[
+                    ("namespace", self.namespace_name.to_string()),
+                    ("table", table_name.to_string()),
+                ]),
+                series_cardinality: self.series_cardinality_metric.recorder(&[
+                    ("namespace", self.namespace_name.to_string()),
+                    ("table", table_name.to_string()),
+                ]),
+                cardinality_sketch: SeriesCardinalitySketch::default(),
+            });
+
+        entry.rows_buffered.inc(rows as u64);
+
+        for &series_hash in series_hashes {
+            entry.cardinality_sketch.observe(series_hash);
+        }
+        entry
+            .series_cardinality
+            .set(entry.cardinality_sketch.estimate());
+    }
+
+    /// Wait until neither `seq` nor any lower sequence number is being
+    /// actively buffered by this namespace, i.e. until it is safe for a
+    /// querier to assume the write is fully readable.
+    ///
+    /// This is a push-based alternative to spin-polling [`Self::progress()`]:
+    /// the caller is woken as soon as an in-flight write completes and the
+    /// readability condition is re-checked, rather than re-polling on a
+    /// timer.
+    pub(crate) async fn wait_readable(&self, seq: SequenceNumber) {
+        loop {
+            // Register for the next notification before checking the
+            // condition, so a notification fired between the check and the
+            // await below is not missed.
+            let notified = self.readable_notify.notified();
+
+            if self.is_readable(seq) {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
+    /// As [`Self::wait_readable()`], but returns [`Elapsed`] if `seq` has not
+    /// become readable within `timeout`, instead of waiting indefinitely.
+    pub(crate) async fn wait_readable_timeout(
+        &self,
+        seq: SequenceNumber,
+        timeout: Duration,
+    ) -> Result<(), Elapsed> {
+        tokio::time::timeout(timeout, self.wait_readable(seq)).await
+    }
+
+    /// Returns true if no sequence number less than or equal to `seq` is
+    /// actively being buffered by this namespace.
+    fn is_readable(&self, seq: SequenceNumber) -> bool {
+        match self.buffering_sequence_numbers.read().keys().next() {
+            // A lower/equal lowest-buffering number means `seq`, or an
+            // earlier write, has not yet been fully applied to all
+            // partitions.
+            Some(&lowest_buffering) => lowest_buffering > seq,
+            None => true,
+        }
+    }
+
     /// Return progress from this Namespace
     pub(super) async fn progress(&self) -> ShardProgress {
         let tables: Vec<_> = self.tables.read().by_id.values().map(Arc::clone).collect();
 
         // Consolidate progress across partitions.
         let mut progress = ShardProgress::new()
-            // Properly account for any sequence number that is
-            // actively buffering and thus not yet completely
-            // readable.
-            .actively_buffering(*self.buffering_sequence_number.read());
+            // Properly account for the lowest sequence number that is
+            // actively buffering and thus not yet completely readable -
+            // many sequence numbers may be buffering concurrently, but only
+            // the oldest of them holds back readability.
+            .actively_buffering(
+                self.buffering_sequence_numbers
+                    .read()
+                    .keys()
+                    .next()
+                    .copied(),
+            );
 
         for table_data in tables {
             progress = progress.combine(table_data.progress())
@@ -313,36 +796,57 @@ impl NamespaceData {
     }
 }
 
-/// RAAI struct that sets buffering sequence number on creation and clears it on free
+/// RAAI struct that adds a buffering sequence number to the in-flight set on
+/// creation, and removes it (once all concurrent buffers for it complete) on
+/// free.
 struct ScopedSequenceNumber<'a> {
     sequence_number: SequenceNumber,
-    buffering_sequence_number: &'a RwLock<Option<SequenceNumber>>,
+    buffering_sequence_numbers: &'a RwLock<BTreeMap<SequenceNumber, usize>>,
+    readable_notify: &'a Notify,
 }
 
 impl<'a> ScopedSequenceNumber<'a> {
     fn new(
         sequence_number: SequenceNumber,
-        buffering_sequence_number: &'a RwLock<Option<SequenceNumber>>,
+        buffering_sequence_numbers: &'a RwLock<BTreeMap<SequenceNumber, usize>>,
+        readable_notify: &'a Notify,
     ) -> Self {
-        *buffering_sequence_number.write() = Some(sequence_number);
+        *buffering_sequence_numbers
+            .write()
+            .entry(sequence_number)
+            .or_insert(0) += 1;
 
         Self {
             sequence_number,
-            buffering_sequence_number,
+            buffering_sequence_numbers,
+            readable_notify,
         }
     }
 }
 
 impl<'a> Drop for ScopedSequenceNumber<'a> {
     fn drop(&mut self) {
-        // clear write on drop
-        let mut buffering_sequence_number = self.buffering_sequence_number.write();
-        assert_eq!(
-            *buffering_sequence_number,
-            Some(self.sequence_number),
-            "multiple operations are being buffered concurrently"
-        );
-        *buffering_sequence_number = None;
+        let mut buffering_sequence_numbers = self.buffering_sequence_numbers.write();
+
+        match buffering_sequence_numbers.entry(self.sequence_number) {
+            Entry::Occupied(mut o) => {
+                *o.get_mut() -= 1;
+                if *o.get() == 0 {
+                    o.remove();
+                }
+            }
+            Entry::Vacant(_) => {
+                panic!(
+                    "buffering sequence number {:?} was not recorded",
+                    self.sequence_number
+                )
+            }
+        }
+        drop(buffering_sequence_numbers);
+
+        // Wake any `wait_readable()` callers so they can re-check whether
+        // their requested sequence number is now readable.
+        self.readable_notify.notify_waiters();
     }
 }
 
@@ -392,6 +896,8 @@ mod tests {
             NAMESPACE_NAME.into(),
             SHARD_ID,
             partition_provider,
+            PartitionTemplate::default(),
+            DeletePolicy::default(),
             &*metrics,
         );
 
@@ -431,4 +937,311 @@ mod tests {
             .fetch();
         assert_eq!(tables, 1);
     }
+
+    /// Build a [`NamespaceData`] with no configured partitions, for tests
+    /// that only exercise behaviour not requiring a real partition lookup.
+    fn new_test_namespace(
+        metrics: &metric::Registry,
+        default_partition_template: PartitionTemplate,
+        delete_policy: DeletePolicy,
+    ) -> NamespaceData {
+        NamespaceData::new(
+            NAMESPACE_ID,
+            NAMESPACE_NAME.into(),
+            SHARD_ID,
+            Arc::new(MockPartitionProvider::default()),
+            default_partition_template,
+            delete_policy,
+            metrics,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wait_readable_unblocks_on_drop_and_times_out() {
+        let metrics = metric::Registry::default();
+        let ns = Arc::new(new_test_namespace(
+            &metrics,
+            PartitionTemplate::default(),
+            DeletePolicy::default(),
+        ));
+
+        let seq = SequenceNumber::new(1);
+
+        // Nothing is buffering yet, so this resolves immediately.
+        ns.wait_readable(seq).await;
+
+        // Hold `seq` as actively buffering.
+        let guard = ScopedSequenceNumber::new(
+            seq,
+            &ns.buffering_sequence_numbers,
+            &ns.readable_notify,
+        );
+
+        // Spawn a waiter so we can prove it is actually blocked on the
+        // guard, not merely re-polling and getting lucky.
+        let waiter = {
+            let ns = Arc::clone(&ns);
+            tokio::spawn(async move { ns.wait_readable(seq).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(
+            !waiter.is_finished(),
+            "waiter must still be blocked while seq is buffering"
+        );
+
+        // A bounded wait must time out while still buffering.
+        ns.wait_readable_timeout(seq, Duration::from_millis(20))
+            .await
+            .expect_err("must not be readable while still buffering");
+
+        // Dropping the guard must notify the waiter and unblock it.
+        drop(guard);
+        tokio::time::timeout(Duration::from_millis(200), waiter)
+            .await
+            .expect("waiter must unblock once the guard drops")
+            .expect("waiter task must not panic");
+
+        // Now readable, so a fresh wait resolves immediately.
+        ns.wait_readable_timeout(seq, Duration::from_millis(20))
+            .await
+            .expect("must be readable once buffering completes");
+    }
+
+    #[test]
+    fn test_scoped_sequence_number_concurrent_refcount() {
+        let buffering = RwLock::new(BTreeMap::new());
+        let notify = Notify::new();
+        let seq = SequenceNumber::new(7);
+
+        // Two concurrent buffers for the same sequence number must not
+        // panic (unlike the old single-slot implementation).
+        let first = ScopedSequenceNumber::new(seq, &buffering, &notify);
+        let second = ScopedSequenceNumber::new(seq, &buffering, &notify);
+        assert_eq!(*buffering.read().get(&seq).expect("must be recorded"), 2);
+
+        // Completing one of the two must decrement, not remove, the entry.
+        drop(first);
+        assert_eq!(*buffering.read().get(&seq).expect("must be recorded"), 1);
+
+        // Completing the last one must remove the entry entirely.
+        drop(second);
+        assert!(buffering.read().get(&seq).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_table_ingest_metrics() {
+        let metrics = Arc::new(metric::Registry::default());
+
+        // The default daily template derives the partition key from the
+        // rows' timestamps (22 and 23 ns after the epoch), not from the
+        // router's key.
+        let partition_provider = Arc::new(MockPartitionProvider::default().with_partition(
+            PartitionData::new(
+                PartitionId::new(0),
+                PartitionKey::from("1970-01-01"),
+                SHARD_ID,
+                NAMESPACE_ID,
+                TABLE_ID,
+                TABLE_NAME.into(),
+                SortKeyState::Provided(None),
+                None,
+            ),
+        ));
+
+        let ns = NamespaceData::new(
+            NAMESPACE_ID,
+            NAMESPACE_NAME.into(),
+            SHARD_ID,
+            partition_provider,
+            PartitionTemplate::default(),
+            DeletePolicy::default(),
+            &*metrics,
+        );
+
+        // Two rows, two distinct series (differing "city" tag value).
+        ns.buffer_operation(
+            DmlOperation::Write(make_write_op(
+                &PartitionKey::from("banana-split"),
+                SHARD_INDEX,
+                NAMESPACE_NAME,
+                NAMESPACE_ID,
+                TABLE_ID,
+                0,
+                "test_table,city=Medford day=\"sun\",temp=55 22\ntest_table,city=Boston day=\"rain\",temp=50 23",
+            )),
+            &MockLifecycleHandle::default(),
+        )
+        .await
+        .expect("buffer op should succeed");
+
+        let attributes = Attributes::from([
+            ("namespace", NAMESPACE_NAME.to_string()),
+            ("table", TABLE_NAME.to_string()),
+        ]);
+
+        let rows_buffered = metrics
+            .get_instrument::<Metric<U64Counter>>("ingester_table_rows_buffered")
+            .expect("failed to read metric")
+            .get_observer(&attributes)
+            .expect("failed to get observer")
+            .fetch();
+        assert_eq!(rows_buffered, 2);
+
+        let series_cardinality = metrics
+            .get_instrument::<Metric<U64Gauge>>("ingester_table_series_cardinality")
+            .expect("failed to read metric")
+            .get_observer(&attributes)
+            .expect("failed to get observer")
+            .fetch();
+        assert_eq!(series_cardinality, 2);
+    }
+
+    #[test]
+    fn test_table_partition_template_override() {
+        let metrics = metric::Registry::default();
+        let ns = new_test_namespace(
+            &metrics,
+            PartitionTemplate::new(PartitionGranularity::Daily, vec![]),
+            DeletePolicy::default(),
+        );
+
+        let table_name: TableName = TABLE_NAME.into();
+
+        // With no override configured, the namespace default applies.
+        assert_eq!(
+            ns.partition_template_for(&table_name).granularity(),
+            PartitionGranularity::Daily
+        );
+
+        ns.set_table_partition_template(
+            table_name.clone(),
+            PartitionTemplate::new(PartitionGranularity::Hourly, vec!["city".to_string()]),
+        );
+
+        // The per-table override now takes effect instead of the default.
+        let overridden = ns.partition_template_for(&table_name);
+        assert_eq!(overridden.granularity(), PartitionGranularity::Hourly);
+        assert_eq!(overridden.tag_columns(), ["city".to_string()]);
+
+        // Other tables are unaffected and still see the namespace default.
+        assert_eq!(
+            ns.partition_template_for(&"other_table".into()).granularity(),
+            PartitionGranularity::Daily
+        );
+    }
+
+    #[test]
+    fn test_partition_key_for_uses_granularity_and_tag_columns() {
+        let table_name: TableName = TABLE_NAME.into();
+        let write = make_write_op(
+            &PartitionKey::from("unused-router-key"),
+            SHARD_INDEX,
+            NAMESPACE_NAME,
+            NAMESPACE_ID,
+            TABLE_ID,
+            0,
+            // 2022-04-01T11:25:45Z
+            r#"test_table,city=Medford day="sun",temp=55 1648812345000000000"#,
+        );
+        let (_, _, batch) = write.into_tables().next().expect("must have one table");
+        let router_key = PartitionKey::from("unused-router-key");
+
+        let daily = PartitionTemplate::new(PartitionGranularity::Daily, vec![]);
+        assert_eq!(
+            partition_key_for(&daily, &router_key, &table_name, &batch)
+                .expect("single bucket must derive a key"),
+            PartitionKey::from("2022-04-01")
+        );
+
+        let hourly_with_tag =
+            PartitionTemplate::new(PartitionGranularity::Hourly, vec!["city".to_string()]);
+        assert_eq!(
+            partition_key_for(&hourly_with_tag, &router_key, &table_name, &batch)
+                .expect("single bucket must derive a key"),
+            PartitionKey::from("2022-04-01-11-Medford")
+        );
+    }
+
+    #[test]
+    fn test_partition_key_for_rejects_batch_spanning_multiple_buckets() {
+        let table_name: TableName = TABLE_NAME.into();
+        let write = make_write_op(
+            &PartitionKey::from("unused-router-key"),
+            SHARD_INDEX,
+            NAMESPACE_NAME,
+            NAMESPACE_ID,
+            TABLE_ID,
+            0,
+            // Same calendar day, but either side of the 11:00-12:00 hour
+            // boundary - the router bucketed this as one daily batch, but
+            // an hourly override must not be able to silently apply the
+            // first row's bucket to both.
+            "test_table,city=Medford day=\"sun\",temp=55 1648812345000000000\n\
+             test_table,city=Medford day=\"sun\",temp=56 1648816000000000000",
+        );
+        let (_, _, batch) = write.into_tables().next().expect("must have one table");
+        let router_key = PartitionKey::from("unused-router-key");
+
+        // Daily granularity: both rows land in the same bucket.
+        let daily = PartitionTemplate::new(PartitionGranularity::Daily, vec![]);
+        assert!(partition_key_for(&daily, &router_key, &table_name, &batch).is_ok());
+
+        // Hourly granularity: the rows straddle an hour boundary, so this
+        // must be rejected rather than silently keyed on the first row.
+        let hourly = PartitionTemplate::new(PartitionGranularity::Hourly, vec![]);
+        assert!(matches!(
+            partition_key_for(&hourly, &router_key, &table_name, &batch),
+            Err(crate::data::Error::PartitionTemplateSpansMultipleBuckets { .. })
+        ));
+    }
+
+    #[test]
+    fn test_partition_key_for_distinguishes_absent_tag_from_empty_tag() {
+        let table_name: TableName = TABLE_NAME.into();
+        let write = make_write_op(
+            &PartitionKey::from("unused-router-key"),
+            SHARD_INDEX,
+            NAMESPACE_NAME,
+            NAMESPACE_ID,
+            TABLE_ID,
+            0,
+            // Same day/hour, but the `city` tag is present (and empty) on
+            // the first row and entirely absent on the second - these are
+            // two distinct series and must not be folded into one bucket.
+            "test_table,city= day=\"sun\",temp=55 1648812345000000000\n\
+             test_table day=\"sun\",temp=56 1648812345000000001",
+        );
+        let (_, _, batch) = write.into_tables().next().expect("must have one table");
+        let router_key = PartitionKey::from("unused-router-key");
+
+        let hourly_with_tag =
+            PartitionTemplate::new(PartitionGranularity::Hourly, vec!["city".to_string()]);
+        assert!(matches!(
+            partition_key_for(&hourly_with_tag, &router_key, &table_name, &batch),
+            Err(crate::data::Error::PartitionTemplateSpansMultipleBuckets { .. })
+        ));
+    }
+
+    #[test]
+    fn test_delete_policy_reject_returns_error() {
+        let metrics = metric::Registry::default();
+        let ns = new_test_namespace(&metrics, PartitionTemplate::default(), DeletePolicy::Reject);
+
+        let err = ns
+            .apply_delete_policy(Some(TABLE_NAME.to_string()), Some(SequenceNumber::new(1)))
+            .expect_err("Reject policy must fail unsupported deletes");
+        assert!(matches!(err, crate::data::Error::DeleteUnsupported { .. }));
+    }
+
+    #[test]
+    fn test_delete_policy_drop_returns_skipped() {
+        let metrics = metric::Registry::default();
+        let ns = new_test_namespace(&metrics, PartitionTemplate::default(), DeletePolicy::Drop);
+
+        let action = ns
+            .apply_delete_policy(Some(TABLE_NAME.to_string()), Some(SequenceNumber::new(1)))
+            .expect("Drop policy must not error");
+        assert!(matches!(action, DmlApplyAction::Skipped));
+    }
 }